@@ -0,0 +1,25 @@
+use std::os::unix::io::RawFd;
+
+// Mirrors the other KERNEL_SU_OPTION-style prctl calls ksud uses to talk to the
+// kernel module.
+const KERNEL_SU_OPTION: libc::c_int = 0xDEADBEEFu32 as libc::c_int;
+const CMD_SULOG_EVENTFD: libc::c_ulong = 20;
+
+/// Ask the kernel for a pollable fd that becomes readable when new sulog lines arrive.
+pub fn sulog_eventfd() -> Option<RawFd> {
+    let mut fd: libc::c_int = -1;
+    let ret = unsafe {
+        libc::prctl(
+            KERNEL_SU_OPTION,
+            CMD_SULOG_EVENTFD,
+            &mut fd as *mut libc::c_int as libc::c_ulong,
+            0,
+            0,
+        )
+    };
+    if ret != 0 || fd < 0 {
+        None
+    } else {
+        Some(fd)
+    }
+}
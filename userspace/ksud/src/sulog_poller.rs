@@ -1,30 +1,491 @@
 use crate::{defs, ksucalls, utils};
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use log::{debug, warn};
+use regex::Regex;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
+use std::sync::Mutex;
 use std::{fs::OpenOptions, io::Write, sync::Once, thread, time::Duration};
-use std::os::unix::io::AsRawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsRawFd, RawFd};
 use libc;
 
 const DEFAULT_POLL_INTERVAL_SECS: u64 = 30;
-const ROTATE_SIZE_BYTES: u64 = 32 * 1024 * 1024;
+const DEFAULT_ROTATE_SIZE_BYTES: u64 = 32 * 1024 * 1024;
+const DEFAULT_MAX_GENERATIONS: u64 = 5;
 const SULOG_FILENAME: &str = "sulog.log";
-const SULOG_OLD_FILENAME: &str = "sulog.old.log";
+const CONFIG_FILENAME: &str = "sulog.conf";
+const PID_FILENAME: &str = "sulog.pid";
 
-/// Return canonical paths for current sulog and rotated sulog.
-fn sulog_paths() -> (PathBuf, PathBuf) {
-    let logdir = Path::new(defs::LOG_DIR);
-    (logdir.join(SULOG_FILENAME), logdir.join(SULOG_OLD_FILENAME))
+/// Tags used to tell apart epoll events for the sulog, config watch and signal fds.
+const SULOG_EVENT_TAG: u64 = 0;
+const CONFIG_EVENT_TAG: u64 = 1;
+const SIGNAL_EVENT_TAG: u64 = 2;
+
+/// Live-tunable poll interval and rotation threshold, updated in place on config reload.
+static POLL_INTERVAL_SECS: AtomicU64 = AtomicU64::new(DEFAULT_POLL_INTERVAL_SECS);
+static ROTATE_SIZE_BYTES: AtomicU64 = AtomicU64::new(DEFAULT_ROTATE_SIZE_BYTES);
+/// How many compressed generations (`sulog.0.log.gz`, `sulog.1.log.gz`, ...) to retain.
+static MAX_GENERATIONS: AtomicU64 = AtomicU64::new(DEFAULT_MAX_GENERATIONS);
+
+/// Set by the SIGTERM/SIGINT handler; polled by the daemon loops to shut down cleanly.
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+/// Write end of the shutdown self-pipe, nudged by the async-signal-safe handler.
+static SIGNAL_WRITE_FD: AtomicI32 = AtomicI32::new(-1);
+
+/// Per-line enrichment/filtering config, re-derived from `sulog.conf` on each reload.
+static LINE_CONFIG: Mutex<Option<LineProcessingConfig>> = Mutex::new(None);
+/// Trailing partial line (no terminating `\n` yet) carried over between poll cycles.
+static PENDING_LINE: Mutex<String> = Mutex::new(String::new());
+
+/// Parsed line-processing config: an optional `%`-directive template plus allow/deny filters.
+struct LineProcessingConfig {
+    template: Option<String>,
+    allow: Option<Regex>,
+    deny: Option<Regex>,
+}
+
+/// Fields available to a line template, extracted from one raw kernel sulog line.
+struct LineFields {
+    timestamp: String,
+    uid: Option<String>,
+    command: Option<String>,
+}
+
+/// Return the canonical path of the live sulog file.
+fn sulog_path() -> PathBuf {
+    Path::new(defs::LOG_DIR).join(SULOG_FILENAME)
+}
+
+/// Return the path of a rotated, gzip-compressed generation (0 = most recent).
+fn rotated_gen_path(logdir: &Path, generation: u64) -> PathBuf {
+    logdir.join(format!("sulog.{generation}.log.gz"))
+}
+
+/// Stream-compress `src` into a gzip file at `dst`.
+fn compress_into(src: &Path, dst: &Path) -> std::io::Result<()> {
+    let mut input = std::fs::File::open(src)?;
+    let output = OpenOptions::new().create(true).write(true).truncate(true).open(dst)?;
+    let mut encoder = GzEncoder::new(output, Compression::default());
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// Parse the generation index out of a `sulog.N.log.gz` filename.
+fn parse_generation_number(filename: &str) -> Option<u64> {
+    filename.strip_prefix("sulog.")?.strip_suffix(".log.gz")?.parse().ok()
+}
+
+/// Return the destination generation for a shift, or `None` if it should be dropped instead.
+fn generation_shift_target(generation: u64, max_generations: u64) -> Option<u64> {
+    if generation + 1 >= max_generations {
+        None
+    } else {
+        Some(generation + 1)
+    }
+}
+
+/// Delete any rotated generation file at or beyond `max_generations`.
+fn remove_generations_beyond(logdir: &Path, max_generations: u64) {
+    let entries = match std::fs::read_dir(logdir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let Some(generation) = parse_generation_number(&name) else {
+            continue;
+        };
+        if generation >= max_generations {
+            if let Err(e) = std::fs::remove_file(entry.path()) {
+                debug!("sulog poller: failed to drop out-of-range generation {generation}: {e}");
+            }
+        }
+    }
 }
 
 /// Rotate sulog file if it exceeds configured threshold.
-fn rotate_if_needed(sulog_path: &Path, old_path: &Path) {
-    if let Ok(meta) = std::fs::metadata(sulog_path) {
-        if meta.len() > ROTATE_SIZE_BYTES {
-            if let Err(e) = std::fs::rename(sulog_path, old_path) {
-                debug!("sulog poller: rotate failed: {e}");
+fn rotate_if_needed(sulog_path: &Path) {
+    let meta = match std::fs::metadata(sulog_path) {
+        Ok(m) => m,
+        Err(_) => return,
+    };
+    if meta.len() <= ROTATE_SIZE_BYTES.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let logdir = Path::new(defs::LOG_DIR);
+    let max_generations = MAX_GENERATIONS.load(Ordering::Relaxed);
+
+    remove_generations_beyond(logdir, max_generations);
+
+    if max_generations == 0 {
+        if let Err(e) = OpenOptions::new().write(true).truncate(true).open(sulog_path) {
+            debug!("sulog poller: failed to truncate sulog with retention disabled: {e}");
+        }
+        return;
+    }
+
+    for generation in (0..max_generations).rev() {
+        let from = rotated_gen_path(logdir, generation);
+        if !from.exists() {
+            continue;
+        }
+        match generation_shift_target(generation, max_generations) {
+            None => {
+                if let Err(e) = std::fs::remove_file(&from) {
+                    debug!("sulog poller: failed to drop oldest rotated generation: {e}");
+                }
+            }
+            Some(to_gen) => {
+                let to = rotated_gen_path(logdir, to_gen);
+                if let Err(e) = std::fs::rename(&from, &to) {
+                    debug!("sulog poller: failed to shift rotated generation {generation}: {e}");
+                }
             }
         }
     }
+
+    if let Err(e) = compress_into(sulog_path, &rotated_gen_path(logdir, 0)) {
+        warn!("sulog poller: failed to compress rotated sulog: {e}");
+        return;
+    }
+    if let Err(e) = OpenOptions::new().write(true).truncate(true).open(sulog_path) {
+        debug!("sulog poller: failed to truncate rotated sulog: {e}");
+    }
+}
+
+/// Return the path to the live-tunable sulog config file.
+fn config_path() -> PathBuf {
+    Path::new(defs::LOG_DIR).join(CONFIG_FILENAME)
+}
+
+/// Re-read `sulog.conf` and apply its keys, keeping prior values for anything missing.
+fn reload_config() {
+    let content = match std::fs::read_to_string(config_path()) {
+        Ok(c) => c,
+        Err(e) => {
+            debug!("sulog poller: config unreadable, keeping current settings: {e}");
+            return;
+        }
+    };
+
+    let mut line_config = LINE_CONFIG.lock().unwrap();
+    let mut template = line_config.as_ref().and_then(|c| c.template.clone());
+    let mut allow = line_config.as_ref().and_then(|c| c.allow.as_ref().map(|r| r.as_str().to_string()));
+    let mut deny = line_config.as_ref().and_then(|c| c.deny.as_ref().map(|r| r.as_str().to_string()));
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            warn!("sulog poller: malformed config line, ignoring: {line}");
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "poll_secs" => match value.parse::<u64>() {
+                Ok(v) if v > 0 => POLL_INTERVAL_SECS.store(v, Ordering::Relaxed),
+                _ => warn!("sulog poller: invalid poll_secs value, keeping current: {value}"),
+            },
+            "rotate_mb" => match value.parse::<u64>() {
+                Ok(v) if v > 0 => ROTATE_SIZE_BYTES.store(v * 1024 * 1024, Ordering::Relaxed),
+                _ => warn!("sulog poller: invalid rotate_mb value, keeping current: {value}"),
+            },
+            "max_generations" => match value.parse::<u64>() {
+                Ok(v) => MAX_GENERATIONS.store(v, Ordering::Relaxed),
+                _ => warn!("sulog poller: invalid max_generations value, keeping current: {value}"),
+            },
+            "line_template" => {
+                if value.is_empty() {
+                    template = None;
+                } else {
+                    let probe = LineFields { timestamp: String::new(), uid: None, command: None };
+                    match render_template(value, &probe) {
+                        Ok(_) => template = Some(value.to_string()),
+                        Err(e) => warn!("sulog poller: invalid line_template, keeping current: {e}"),
+                    }
+                }
+            }
+            "allow_regex" => {
+                if value.is_empty() {
+                    allow = None;
+                } else {
+                    match Regex::new(value) {
+                        Ok(_) => allow = Some(value.to_string()),
+                        Err(e) => warn!("sulog poller: invalid allow_regex, keeping current: {e}"),
+                    }
+                }
+            }
+            "deny_regex" => {
+                if value.is_empty() {
+                    deny = None;
+                } else {
+                    match Regex::new(value) {
+                        Ok(_) => deny = Some(value.to_string()),
+                        Err(e) => warn!("sulog poller: invalid deny_regex, keeping current: {e}"),
+                    }
+                }
+            }
+            other => warn!("sulog poller: unknown config key, ignoring: {other}"),
+        }
+    }
+
+    *line_config = if template.is_none() && allow.is_none() && deny.is_none() {
+        None
+    } else {
+        Some(LineProcessingConfig {
+            template,
+            allow: allow.and_then(|p| Regex::new(&p).ok()),
+            deny: deny.and_then(|p| Regex::new(&p).ok()),
+        })
+    };
+}
+
+/// Watch the log directory for sulog.conf creation/edits, or `None` if inotify isn't available.
+fn init_config_watch() -> Option<RawFd> {
+    let inotify_fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+    if inotify_fd < 0 {
+        debug!("sulog poller: inotify_init1 failed: {}", std::io::Error::last_os_error());
+        return None;
+    }
+
+    let logdir = Path::new(defs::LOG_DIR);
+    let cpath = match std::ffi::CString::new(logdir.as_os_str().as_bytes()) {
+        Ok(c) => c,
+        Err(_) => {
+            unsafe { libc::close(inotify_fd) };
+            return None;
+        }
+    };
+    let wd = unsafe {
+        libc::inotify_add_watch(
+            inotify_fd,
+            cpath.as_ptr(),
+            libc::IN_CREATE | libc::IN_MODIFY | libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO,
+        )
+    };
+    if wd < 0 {
+        debug!("sulog poller: inotify_add_watch failed: {}", std::io::Error::last_os_error());
+        unsafe { libc::close(inotify_fd) };
+        return None;
+    }
+    Some(inotify_fd)
+}
+
+/// Drain pending inotify events off `fd`, returning whether any named the sulog config file.
+fn drain_config_events(fd: RawFd) -> bool {
+    let mut buf = [0u8; 4096];
+    let header_len = std::mem::size_of::<libc::inotify_event>();
+    let mut config_event = false;
+    loop {
+        let n = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        if n <= 0 {
+            break;
+        }
+        let n = n as usize;
+        let mut offset = 0;
+        while offset + header_len <= n {
+            let event = unsafe { &*(buf.as_ptr().add(offset) as *const libc::inotify_event) };
+            let name_len = event.len as usize;
+            if name_len > 0 && offset + header_len + name_len <= n {
+                let name = &buf[offset + header_len..offset + header_len + name_len];
+                let name_end = name.iter().position(|&b| b == 0).unwrap_or(name_len);
+                if &name[..name_end] == CONFIG_FILENAME.as_bytes() {
+                    config_event = true;
+                }
+            }
+            offset += header_len + name_len;
+        }
+    }
+    config_event
+}
+
+/// Render the current local wall-clock time as `YYYY-MM-DD HH:MM:SS`.
+fn format_local_time() -> String {
+    unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut tm);
+        let fmt = b"%Y-%m-%d %H:%M:%S\0";
+        let mut buf = [0u8; 32];
+        let len = libc::strftime(
+            buf.as_mut_ptr() as *mut libc::c_char,
+            buf.len(),
+            fmt.as_ptr() as *const libc::c_char,
+            &tm,
+        );
+        String::from_utf8_lossy(&buf[..len]).into_owned()
+    }
+}
+
+/// Best-effort extraction of `uid=`/`comm=` tokens out of a raw kernel sulog line.
+fn parse_line_fields(raw_line: &str) -> (Option<String>, Option<String>) {
+    let uid = raw_line
+        .split_whitespace()
+        .find_map(|tok| tok.strip_prefix("uid="))
+        .map(str::to_string);
+    let command = raw_line
+        .split_whitespace()
+        .find_map(|tok| tok.strip_prefix("comm="))
+        .map(str::to_string);
+    (uid, command)
+}
+
+/// Render a `%`-directive template (`%t` time, `%u` uid, `%c` command, `%%` percent).
+fn render_template(template: &str, fields: &LineFields) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            None => return Err("unterminated '%' directive at end of template".to_string()),
+            Some('t') => out.push_str(&fields.timestamp),
+            Some('u') => out.push_str(fields.uid.as_deref().unwrap_or("-")),
+            Some('c') => out.push_str(fields.command.as_deref().unwrap_or("-")),
+            Some('%') => out.push('%'),
+            Some(other) => return Err(format!("unknown format directive '%{other}'")),
+        }
+    }
+    Ok(out)
+}
+
+/// Apply the configured deny/allow filters and template to a single complete line.
+fn process_one_line(line: &str, config: &LineProcessingConfig) -> Option<String> {
+    if let Some(deny) = &config.deny {
+        if deny.is_match(line) {
+            return None;
+        }
+    }
+    if let Some(allow) = &config.allow {
+        if !allow.is_match(line) {
+            return None;
+        }
+    }
+    match &config.template {
+        Some(template) => {
+            let (uid, command) = parse_line_fields(line);
+            let fields = LineFields { timestamp: format_local_time(), uid, command };
+            match render_template(template, &fields) {
+                Ok(rendered) => Some(rendered),
+                Err(e) => {
+                    warn!("sulog poller: template render failed, passing line through: {e}");
+                    Some(line.to_string())
+                }
+            }
+        }
+        None => Some(line.to_string()),
+    }
+}
+
+/// Split freshly fetched content into complete lines and run each through the configured filter/template.
+fn process_lines(content: &str) -> String {
+    let mut complete_lines = Vec::new();
+    {
+        let mut pending = PENDING_LINE.lock().unwrap();
+        pending.push_str(content);
+        while let Some(pos) = pending.find('\n') {
+            complete_lines.push(pending[..pos].to_string());
+            pending.drain(..=pos);
+        }
+    }
+
+    if complete_lines.is_empty() {
+        return String::new();
+    }
+
+    let config = LINE_CONFIG.lock().unwrap();
+    let mut out = String::new();
+    for line in &complete_lines {
+        let rendered = match config.as_ref() {
+            Some(cfg) => process_one_line(line, cfg),
+            None => Some(line.clone()),
+        };
+        if let Some(rendered) = rendered {
+            out.push_str(&rendered);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Acquire the daemon's single-instance lock on `sulog.pid`, exiting if already held.
+fn acquire_pidlock() -> std::fs::File {
+    let logdir = Path::new(defs::LOG_DIR);
+    if let Err(e) = utils::ensure_dir_exists(logdir) {
+        warn!("sulog poller: ensure log dir failed for pidfile: {e}");
+    }
+
+    let file = match OpenOptions::new().create(true).write(true).open(logdir.join(PID_FILENAME)) {
+        Ok(f) => f,
+        Err(e) => {
+            warn!("sulog poller: failed to open pidfile: {e}");
+            unsafe { libc::_exit(1) };
+        }
+    };
+
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        debug!("sulog poller: another daemon instance holds the pidfile lock, exiting");
+        unsafe { libc::_exit(0) };
+    }
+
+    let mut file = file;
+    let _ = file.set_len(0);
+    if let Err(e) = writeln!(file, "{}", unsafe { libc::getpid() }) {
+        warn!("sulog poller: failed to write pidfile: {e}");
+    }
+    let _ = file.flush();
+    file
+}
+
+/// Remove the pidfile on clean shutdown so a future launch doesn't see a stale entry.
+fn remove_pidfile() {
+    let pid_path = Path::new(defs::LOG_DIR).join(PID_FILENAME);
+    if let Err(e) = std::fs::remove_file(&pid_path) {
+        debug!("sulog poller: failed to remove pidfile: {e}");
+    }
+}
+
+/// Async-signal-safe SIGTERM/SIGINT handler: sets the shutdown flag and nudges the self-pipe.
+extern "C" fn handle_shutdown_signal(_sig: libc::c_int) {
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+    let fd = SIGNAL_WRITE_FD.load(Ordering::SeqCst);
+    if fd >= 0 {
+        let byte: u8 = 1;
+        unsafe {
+            libc::write(fd, &byte as *const u8 as *const libc::c_void, 1);
+        }
+    }
+}
+
+/// Install SIGTERM/SIGINT handlers backed by a self-pipe, returning its read end.
+fn install_shutdown_signal_pipe() -> Option<RawFd> {
+    let mut fds: [RawFd; 2] = [-1, -1];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) } < 0 {
+        warn!("sulog poller: failed to create signal pipe: {}", std::io::Error::last_os_error());
+        return None;
+    }
+    let (read_fd, write_fd) = (fds[0], fds[1]);
+    SIGNAL_WRITE_FD.store(write_fd, Ordering::SeqCst);
+
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handle_shutdown_signal as usize;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGTERM, &action, std::ptr::null_mut());
+        libc::sigaction(libc::SIGINT, &action, std::ptr::null_mut());
+    }
+
+    Some(read_fd)
 }
 
 /// Persist fetched content to sulog file; returns Ok(()) on success.
@@ -33,17 +494,27 @@ fn persist_sulog_content(content: &str) -> Result<(), ()> {
         return Ok(());
     }
 
+    let processed = process_lines(content);
+    if processed.is_empty() {
+        return Ok(());
+    }
+
+    write_sulog(&processed)
+}
+
+/// Append `data` to the live sulog file, rotating first if needed.
+fn write_sulog(data: &str) -> Result<(), ()> {
     let logdir = Path::new(defs::LOG_DIR);
     utils::ensure_dir_exists(logdir).map_err(|_| {
         warn!("sulog poller: ensure log dir failed");
     })?;
 
-    let (sulog_path, old_path) = sulog_paths();
-    rotate_if_needed(&sulog_path, &old_path);
+    let sulog_path = sulog_path();
+    rotate_if_needed(&sulog_path);
 
     match OpenOptions::new().create(true).append(true).open(&sulog_path) {
         Ok(mut f) => {
-            if let Err(e) = f.write_all(content.as_bytes()) {
+            if let Err(e) = f.write_all(data.as_bytes()) {
                 warn!("sulog poller: failed to write sulog: {e}");
                 return Err(());
             }
@@ -56,6 +527,17 @@ fn persist_sulog_content(content: &str) -> Result<(), ()> {
     }
 }
 
+/// Flush any unterminated fragment left in `PENDING_LINE` so it isn't silently discarded.
+fn flush_pending_line() {
+    let remaining = std::mem::take(&mut *PENDING_LINE.lock().unwrap());
+    if remaining.is_empty() {
+        return;
+    }
+    if write_sulog(&remaining).is_err() {
+        debug!("sulog poller: failed to flush pending line");
+    }
+}
+
 fn fetch_and_persist_once() {
     match ksucalls::fetch_sulog() {
         Ok(content) => {
@@ -67,6 +549,136 @@ fn fetch_and_persist_once() {
     }
 }
 
+/// Drain a readable fd so a stale event doesn't cause `epoll_wait` to spin.
+fn drain_fd(fd: RawFd) {
+    let mut buf = [0u8; 256];
+    loop {
+        let n = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        if n <= 0 {
+            break;
+        }
+    }
+}
+
+/// Poll loop driven by `epoll_wait`, falling back to `run_sleep_loop` if epoll setup fails.
+fn run_poll_loop(initial_poll_interval: u64, signal_fd: Option<RawFd>) {
+    POLL_INTERVAL_SECS.store(initial_poll_interval, Ordering::Relaxed);
+    reload_config();
+
+    let epfd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+    if epfd < 0 {
+        debug!("sulog poller: epoll_create1 failed, falling back to timed loop");
+        return run_sleep_loop();
+    }
+
+    let sulog_fd = match ksucalls::sulog_eventfd() {
+        Some(fd) => fd,
+        None => {
+            debug!("sulog poller: sulog_eventfd unavailable, falling back to timed loop");
+            unsafe { libc::close(epfd) };
+            return run_sleep_loop();
+        }
+    };
+
+    let mut sulog_ev = libc::epoll_event {
+        events: libc::EPOLLIN as u32,
+        u64: SULOG_EVENT_TAG,
+    };
+    if unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, sulog_fd, &mut sulog_ev) } < 0 {
+        warn!("sulog poller: epoll_ctl failed, falling back to timed loop");
+        unsafe { libc::close(epfd) };
+        return run_sleep_loop();
+    }
+
+    let config_fd = init_config_watch();
+    if let Some(fd) = config_fd {
+        let mut config_ev = libc::epoll_event {
+            events: libc::EPOLLIN as u32,
+            u64: CONFIG_EVENT_TAG,
+        };
+        if unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, fd, &mut config_ev) } < 0 {
+            debug!("sulog poller: failed to watch sulog.conf: {}", std::io::Error::last_os_error());
+            unsafe { libc::close(fd) };
+        }
+    }
+
+    if let Some(fd) = signal_fd {
+        let mut signal_ev = libc::epoll_event {
+            events: libc::EPOLLIN as u32,
+            u64: SIGNAL_EVENT_TAG,
+        };
+        if unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, fd, &mut signal_ev) } < 0 {
+            warn!("sulog poller: failed to watch shutdown signal pipe: {}", std::io::Error::last_os_error());
+        }
+    }
+
+    let mut events: [libc::epoll_event; 3] = unsafe { std::mem::zeroed() };
+    while !SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+        let timeout_ms = (POLL_INTERVAL_SECS.load(Ordering::Relaxed).saturating_mul(1000))
+            .min(i32::MAX as u64) as i32;
+        let n = unsafe {
+            libc::epoll_wait(epfd, events.as_mut_ptr(), events.len() as i32, timeout_ms)
+        };
+        if n < 0 {
+            debug!("sulog poller: epoll_wait failed: {}", std::io::Error::last_os_error());
+        }
+
+        let mut config_changed = false;
+        for ev in events.iter().take(n.max(0) as usize) {
+            match ev.u64 {
+                SULOG_EVENT_TAG => drain_fd(sulog_fd),
+                CONFIG_EVENT_TAG => {
+                    if let Some(fd) = config_fd {
+                        if drain_config_events(fd) {
+                            config_changed = true;
+                        }
+                    }
+                }
+                SIGNAL_EVENT_TAG => {
+                    if let Some(fd) = signal_fd {
+                        drain_fd(fd);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+            break;
+        }
+        if config_changed {
+            reload_config();
+        }
+        // A readable sulog event means new lines; a timeout is the periodic safety sweep.
+        fetch_and_persist_once();
+    }
+}
+
+/// Fallback loop: fetch on the currently configured sleep interval.
+fn run_sleep_loop() {
+    while !SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+        fetch_and_persist_once();
+        thread::sleep(Duration::from_secs(POLL_INTERVAL_SECS.load(Ordering::Relaxed)));
+    }
+}
+
+/// Run the detached daemon to completion, holding the pidfile lock until shutdown cleanup. Never returns.
+fn run_daemon(poll_interval: u64) -> ! {
+    let pidlock = acquire_pidlock();
+    let signal_fd = install_shutdown_signal_pipe();
+
+    run_poll_loop(poll_interval, signal_fd);
+
+    // Final sweep so nothing fetched just before the signal arrived is lost.
+    fetch_and_persist_once();
+    flush_pending_line();
+
+    // Release the flock before unlinking, so a racing launch never sees the pidfile
+    // gone while we still hold the lock on its old inode.
+    drop(pidlock);
+    remove_pidfile();
+    unsafe { libc::_exit(0) };
+}
+
 /// Start the sulog background poller (idempotent).
 /// It will be spawned once per process and will poll kernel periodically.
 pub fn start() {
@@ -87,10 +699,7 @@ pub fn start() {
                     // fallback to threaded poller
                     let _ = thread::Builder::new()
                         .name("ksud-sulog-poller".to_string())
-                        .spawn(move || loop {
-                            fetch_and_persist_once();
-                            thread::sleep(Duration::from_secs(poll_interval));
-                        });
+                        .spawn(move || run_poll_loop(poll_interval, None));
                 }
                 0 => {
                     // Child: create new session
@@ -111,11 +720,9 @@ pub fn start() {
                                 let _ = libc::dup2(fd, libc::STDERR_FILENO);
                             }
 
-                            // Poll loop - this is the persistent daemon process
-                            loop {
-                                fetch_and_persist_once();
-                                thread::sleep(Duration::from_secs(poll_interval));
-                            }
+                            // Single-instance daemon process: takes the pidfile lock,
+                            // handles SIGTERM/SIGINT, and runs the poll loop.
+                            run_daemon(poll_interval);
                         }
                         _ => {
                             // Child exits
@@ -130,3 +737,74 @@ pub fn start() {
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(uid: Option<&str>, command: Option<&str>) -> LineFields {
+        LineFields {
+            timestamp: "2024-01-01 00:00:00".to_string(),
+            uid: uid.map(str::to_string),
+            command: command.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn render_template_substitutes_known_directives() {
+        let f = fields(Some("0"), Some("su"));
+        assert_eq!(
+            render_template("%t uid=%u comm=%c", &f).unwrap(),
+            "2024-01-01 00:00:00 uid=0 comm=su"
+        );
+    }
+
+    #[test]
+    fn render_template_falls_back_to_dash_for_missing_fields() {
+        let f = fields(None, None);
+        assert_eq!(render_template("uid=%u comm=%c", &f).unwrap(), "uid=- comm=-");
+    }
+
+    #[test]
+    fn render_template_handles_literal_percent() {
+        let f = fields(None, None);
+        assert_eq!(render_template("100%%", &f).unwrap(), "100%");
+    }
+
+    #[test]
+    fn render_template_rejects_unterminated_directive() {
+        let f = fields(None, None);
+        assert!(render_template("trailing %", &f).is_err());
+    }
+
+    #[test]
+    fn render_template_rejects_unknown_directive() {
+        let f = fields(None, None);
+        assert!(render_template("%z", &f).is_err());
+    }
+
+    #[test]
+    fn parse_generation_number_parses_valid_names() {
+        assert_eq!(parse_generation_number("sulog.0.log.gz"), Some(0));
+        assert_eq!(parse_generation_number("sulog.4.log.gz"), Some(4));
+    }
+
+    #[test]
+    fn parse_generation_number_rejects_unrelated_names() {
+        assert_eq!(parse_generation_number("sulog.log"), None);
+        assert_eq!(parse_generation_number("sulog.conf"), None);
+        assert_eq!(parse_generation_number("sulog.log.gz"), None);
+    }
+
+    #[test]
+    fn generation_shift_target_shifts_within_range() {
+        assert_eq!(generation_shift_target(0, 5), Some(1));
+        assert_eq!(generation_shift_target(3, 5), Some(4));
+    }
+
+    #[test]
+    fn generation_shift_target_drops_at_cap() {
+        assert_eq!(generation_shift_target(4, 5), None);
+        assert_eq!(generation_shift_target(1, 2), None);
+    }
+}